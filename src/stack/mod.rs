@@ -2,6 +2,11 @@ mod share;
 mod tcp;
 mod udp;
 
-pub use share::{SharableStack, SharedStack};
-pub use tcp::{StackAndSocket, TcpClientStack, TcpFullStack};
+pub use share::{
+	EphemeralPortStack, PooledSocket, SharableStack, SharedStack, DEFAULT_EPHEMERAL_PORT_RANGE,
+	MAX_LEASED_PORTS,
+};
+pub use tcp::{
+	Incoming, Shutdown, StackAndSocket, TcpClientStack, TcpError, TcpErrorKind, TcpFullStack,
+};
 pub use udp::{UdpClientStack, UdpFullStack};
@@ -0,0 +1,435 @@
+use core::cell::RefCell;
+use core::ops::RangeInclusive;
+
+use no_std_net::SocketAddr;
+
+use super::{TcpClientStack, TcpErrorKind, TcpFullStack};
+
+/// Implemented by a [`TcpClientStack`] that is safe to drive through a shared `&self` reference,
+/// so that a single stack instance can be multiplexed between independent owners of sockets (for
+/// example several protocol clients layered over one `smoltcp` interface) via [`SharedStack`].
+pub trait SharableStack: TcpClientStack {}
+
+impl<T: TcpClientStack> SharableStack for T {}
+
+/// Wraps a [`SharableStack`] in a [`RefCell`] so that `&SharedStack<T>` itself implements
+/// [`TcpClientStack`], letting multiple owners each hold a shared reference instead of requiring
+/// exclusive `&mut` access to the underlying stack.
+pub struct SharedStack<T> {
+	stack: RefCell<T>,
+}
+
+impl<T: SharableStack> SharedStack<T> {
+	/// wrap `stack` so it can be shared between multiple socket owners
+	pub fn new(stack: T) -> Self {
+		SharedStack {
+			stack: RefCell::new(stack),
+		}
+	}
+}
+
+impl<T: SharableStack> TcpClientStack for &SharedStack<T> {
+	type TcpSocket = T::TcpSocket;
+	type Error = T::Error;
+
+	fn socket(&mut self) -> Result<Self::TcpSocket, Self::Error> {
+		self.stack.borrow_mut().socket()
+	}
+
+	fn connect(
+		&mut self,
+		socket: &mut Self::TcpSocket,
+		remote: SocketAddr,
+	) -> nb::Result<(), Self::Error> {
+		self.stack.borrow_mut().connect(socket, remote)
+	}
+
+	fn is_connected(&mut self, socket: &Self::TcpSocket) -> Result<bool, Self::Error> {
+		self.stack.borrow_mut().is_connected(socket)
+	}
+
+	fn send(
+		&mut self,
+		socket: &mut Self::TcpSocket,
+		buffer: &[u8],
+	) -> nb::Result<usize, Self::Error> {
+		self.stack.borrow_mut().send(socket, buffer)
+	}
+
+	fn receive(
+		&mut self,
+		socket: &mut Self::TcpSocket,
+		buffer: &mut [u8],
+	) -> nb::Result<usize, Self::Error> {
+		self.stack.borrow_mut().receive(socket, buffer)
+	}
+
+	fn close(&mut self, socket: Self::TcpSocket) -> Result<(), Self::Error> {
+		self.stack.borrow_mut().close(socket)
+	}
+}
+
+/// Default range of ephemeral ports handed out by [`EphemeralPortStack`], matching the common
+/// IANA-suggested dynamic/private port range.
+pub const DEFAULT_EPHEMERAL_PORT_RANGE: RangeInclusive<u16> = 49152..=65535;
+
+/// Maximum number of ephemeral-port sockets an [`EphemeralPortStack`] will lease out at once.
+///
+/// Every port it hands out is tracked in a fixed-size table of this size for as long as the
+/// socket holding it is open, so a wrapped-around counter can never reissue a port that's still
+/// bound to a live socket. Once this many sockets are simultaneously holding a leased port,
+/// `connect` on a new one fails with [`TcpErrorKind::Other`] until one of them is closed.
+pub const MAX_LEASED_PORTS: usize = 16;
+
+/// A [`TcpFullStack::TcpSocket`] paired with the ephemeral port, if any, that an
+/// [`EphemeralPortStack`] bound it to, so the port can be released back to the lease table on
+/// close without needing a separate socket-to-port lookup.
+///
+/// Note that only the *port* is managed this way: the inner [`TcpFullStack::TcpSocket`] handle
+/// itself is created and destroyed straight through to the wrapped stack, not pooled or reused.
+pub struct PooledSocket<S> {
+	socket: S,
+	port: Option<u16>,
+}
+
+/// Wraps a [`TcpFullStack`] and hands out ephemeral local ports automatically, so that callers
+/// can `connect` a freshly created socket without first picking and binding a local port
+/// themselves. This folds the ephemeral-port-counter bookkeeping that downstream `smoltcp`
+/// integrations otherwise each reimplement into the crate.
+///
+/// This only manages *port numbers* — it does not pool or reuse the underlying
+/// [`TcpFullStack::TcpSocket`] handles, which are created and closed straight through to the
+/// wrapped stack.
+///
+/// Ports are handed out in order starting from the bottom of `range` (or wherever
+/// [`with_port_range_starting_at`](Self::with_port_range_starting_at) was told to start),
+/// wrapping back around once the top of `range` is reached and skipping over any port still
+/// leased to an open socket.
+///
+/// **At most [`MAX_LEASED_PORTS`] sockets may hold a leased port at once**, regardless of how
+/// wide `range` is — this is a fixed-capacity, no-alloc tracking table, not a dynamic pool.
+/// Beyond that ceiling, `connect` returns [`TcpErrorKind::Other`] rather than risk handing out a
+/// port that's still in use.
+pub struct EphemeralPortStack<T> {
+	stack: T,
+	range: RangeInclusive<u16>,
+	next_port: u16,
+	/// ports currently leased to an open socket, so the counter can skip over them on wraparound
+	leased: [Option<u16>; MAX_LEASED_PORTS],
+}
+
+impl<T: TcpFullStack> EphemeralPortStack<T> {
+	/// wrap `stack`, allocating ports from [`DEFAULT_EPHEMERAL_PORT_RANGE`]
+	pub fn new(stack: T) -> Self {
+		Self::with_port_range(stack, DEFAULT_EPHEMERAL_PORT_RANGE)
+	}
+
+	/// wrap `stack`, allocating ports from the given inclusive range, starting at `range`'s
+	/// lower bound
+	pub fn with_port_range(stack: T, range: RangeInclusive<u16>) -> Self {
+		let next_port = *range.start();
+		Self::with_port_range_starting_at(stack, range, next_port)
+	}
+
+	/// wrap `stack`, allocating ports from the given inclusive range, starting the counter at
+	/// `next_port` instead of `range`'s lower bound (e.g. to resume allocation after a restart
+	/// that remembers the last port it handed out). `next_port` is clamped into `range` if it
+	/// falls outside it.
+	pub fn with_port_range_starting_at(
+		stack: T,
+		range: RangeInclusive<u16>,
+		next_port: u16,
+	) -> Self {
+		let next_port = next_port.clamp(*range.start(), *range.end());
+		EphemeralPortStack {
+			stack,
+			range,
+			next_port,
+			leased: [None; MAX_LEASED_PORTS],
+		}
+	}
+
+	/// Lease the next free port, or `None` if every port in `range` is currently leased or the
+	/// lease table itself ([`MAX_LEASED_PORTS`] entries) is full.
+	fn lease_port(&mut self) -> Option<u16> {
+		let slot = self.leased.iter().position(Option::is_none)?;
+		let span = u32::from(*self.range.end()) - u32::from(*self.range.start()) + 1;
+		for _ in 0..span {
+			let candidate = self.next_port;
+			self.next_port = if candidate >= *self.range.end() {
+				*self.range.start()
+			} else {
+				candidate + 1
+			};
+			if !self.leased.iter().any(|leased| *leased == Some(candidate)) {
+				self.leased[slot] = Some(candidate);
+				return Some(candidate);
+			}
+		}
+		None
+	}
+
+	/// Release a port leased by [`lease_port`](Self::lease_port) back to the pool.
+	fn release_port(&mut self, port: u16) {
+		if let Some(slot) = self.leased.iter_mut().find(|leased| **leased == Some(port)) {
+			*slot = None;
+		}
+	}
+}
+
+impl<T: TcpFullStack> TcpClientStack for EphemeralPortStack<T> {
+	type TcpSocket = PooledSocket<T::TcpSocket>;
+	type Error = T::Error;
+
+	fn socket(&mut self) -> Result<Self::TcpSocket, Self::Error> {
+		Ok(PooledSocket {
+			socket: self.stack.socket()?,
+			port: None,
+		})
+	}
+
+	fn connect(
+		&mut self,
+		socket: &mut Self::TcpSocket,
+		remote: SocketAddr,
+	) -> nb::Result<(), Self::Error> {
+		if socket.port.is_none() {
+			let port = self
+				.lease_port()
+				.ok_or_else(|| nb::Error::Other(TcpErrorKind::Other.into()))?;
+			self.stack.bind(&mut socket.socket, port).map_err(|e| {
+				// bind failed: the lease never attached to a socket, so give the port back
+				// instead of holding the slot forever and leaking a lease on every retry.
+				self.release_port(port);
+				nb::Error::Other(e)
+			})?;
+			socket.port = Some(port);
+		}
+		self.stack.connect(&mut socket.socket, remote)
+	}
+
+	fn is_connected(&mut self, socket: &Self::TcpSocket) -> Result<bool, Self::Error> {
+		self.stack.is_connected(&socket.socket)
+	}
+
+	fn send(
+		&mut self,
+		socket: &mut Self::TcpSocket,
+		buffer: &[u8],
+	) -> nb::Result<usize, Self::Error> {
+		self.stack.send(&mut socket.socket, buffer)
+	}
+
+	fn receive(
+		&mut self,
+		socket: &mut Self::TcpSocket,
+		buffer: &mut [u8],
+	) -> nb::Result<usize, Self::Error> {
+		self.stack.receive(&mut socket.socket, buffer)
+	}
+
+	fn close(&mut self, socket: Self::TcpSocket) -> Result<(), Self::Error> {
+		let PooledSocket { socket, port } = socket;
+		self.stack.close(socket)?;
+		if let Some(port) = port {
+			self.release_port(port);
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use no_std_net::{IpAddr, Ipv4Addr};
+
+	use super::super::TcpError;
+	use super::*;
+
+	#[derive(Debug)]
+	enum MockError {
+		/// the mock stack's own `bind` failure
+		Bind,
+		/// a portable error manufactured from a [`TcpErrorKind`], e.g. on lease exhaustion
+		Kind(TcpErrorKind),
+	}
+
+	impl TcpError for MockError {
+		fn kind(&self) -> TcpErrorKind {
+			match self {
+				MockError::Bind => TcpErrorKind::Other,
+				MockError::Kind(kind) => *kind,
+			}
+		}
+	}
+
+	impl From<TcpErrorKind> for MockError {
+		fn from(kind: TcpErrorKind) -> Self {
+			MockError::Kind(kind)
+		}
+	}
+
+	struct MockSocket(u32);
+
+	struct MockStack {
+		next_id: u32,
+		bind_fails: bool,
+	}
+
+	impl MockStack {
+		fn new() -> Self {
+			MockStack {
+				next_id: 0,
+				bind_fails: false,
+			}
+		}
+	}
+
+	impl TcpClientStack for MockStack {
+		type TcpSocket = MockSocket;
+		type Error = MockError;
+
+		fn socket(&mut self) -> Result<Self::TcpSocket, Self::Error> {
+			let id = self.next_id;
+			self.next_id += 1;
+			Ok(MockSocket(id))
+		}
+
+		fn connect(
+			&mut self,
+			_socket: &mut Self::TcpSocket,
+			_remote: SocketAddr,
+		) -> nb::Result<(), Self::Error> {
+			Ok(())
+		}
+
+		fn is_connected(&mut self, _socket: &Self::TcpSocket) -> Result<bool, Self::Error> {
+			Ok(true)
+		}
+
+		fn send(
+			&mut self,
+			_socket: &mut Self::TcpSocket,
+			buffer: &[u8],
+		) -> nb::Result<usize, Self::Error> {
+			Ok(buffer.len())
+		}
+
+		fn receive(
+			&mut self,
+			_socket: &mut Self::TcpSocket,
+			_buffer: &mut [u8],
+		) -> nb::Result<usize, Self::Error> {
+			Ok(0)
+		}
+
+		fn close(&mut self, _socket: Self::TcpSocket) -> Result<(), Self::Error> {
+			Ok(())
+		}
+	}
+
+	impl TcpFullStack for MockStack {
+		fn bind(&mut self, _socket: &mut Self::TcpSocket, _local_port: u16) -> Result<(), Self::Error> {
+			if self.bind_fails {
+				Err(MockError::Bind)
+			} else {
+				Ok(())
+			}
+		}
+
+		fn listen(&mut self, _socket: &mut Self::TcpSocket) -> Result<(), Self::Error> {
+			Ok(())
+		}
+
+		fn accept(
+			&mut self,
+			_socket: &mut Self::TcpSocket,
+		) -> nb::Result<(Self::TcpSocket, SocketAddr), Self::Error> {
+			Err(nb::Error::WouldBlock)
+		}
+	}
+
+	fn remote() -> SocketAddr {
+		SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 80)
+	}
+
+	#[test]
+	fn freed_port_is_reused_after_close() {
+		let mut stack = EphemeralPortStack::with_port_range(MockStack::new(), 49152..=49152);
+
+		let mut a = stack.socket().unwrap();
+		stack.connect(&mut a, remote()).unwrap();
+		let port_a = a.port.unwrap();
+		stack.close(a).unwrap();
+
+		let mut b = stack.socket().unwrap();
+		stack.connect(&mut b, remote()).unwrap();
+		assert_eq!(b.port, Some(port_a));
+	}
+
+	#[test]
+	fn wraparound_skips_a_port_still_leased_to_an_open_socket() {
+		let mut stack = EphemeralPortStack::with_port_range(MockStack::new(), 49152..=49153);
+
+		let mut a = stack.socket().unwrap();
+		stack.connect(&mut a, remote()).unwrap();
+		let mut b = stack.socket().unwrap();
+		stack.connect(&mut b, remote()).unwrap();
+
+		assert_ne!(a.port, b.port);
+
+		// the counter has now wrapped back to 49152, which `a` still holds; once `b`'s port is
+		// freed, the next lease must skip 49152 and hand out `b`'s old port instead
+		let port_b = b.port;
+		stack.close(b).unwrap();
+		let mut c = stack.socket().unwrap();
+		stack.connect(&mut c, remote()).unwrap();
+		assert_eq!(c.port, port_b);
+		assert_ne!(c.port, a.port);
+	}
+
+	#[test]
+	fn exhausted_range_errors_instead_of_reusing_a_live_port() {
+		let mut stack = EphemeralPortStack::with_port_range(MockStack::new(), 49152..=49152);
+
+		let mut a = stack.socket().unwrap();
+		stack.connect(&mut a, remote()).unwrap();
+
+		let mut b = stack.socket().unwrap();
+		assert!(stack.connect(&mut b, remote()).is_err());
+	}
+
+	#[test]
+	fn failed_bind_releases_its_lease_instead_of_exhausting_the_table() {
+		let mut stack = EphemeralPortStack::with_port_range(
+			MockStack {
+				bind_fails: true,
+				..MockStack::new()
+			},
+			49152..=49152,
+		);
+
+		// bind fails every time, so if a lease leaked on error the single port in the range
+		// would stay leased, and every attempt after the first would get TcpErrorKind::Other
+		// from lease_port instead of the stack's own bind error.
+		for _ in 0..(MAX_LEASED_PORTS + 1) {
+			let mut socket = stack.socket().unwrap();
+			match stack.connect(&mut socket, remote()) {
+				Err(nb::Error::Other(MockError::Bind)) => {}
+				other => panic!("expected the stack's own bind error, got {other:?}"),
+			}
+		}
+	}
+
+	#[test]
+	fn with_port_range_starting_at_seeds_the_counter() {
+		let mut stack = EphemeralPortStack::with_port_range_starting_at(
+			MockStack::new(),
+			49152..=49153,
+			49153,
+		);
+
+		let mut a = stack.socket().unwrap();
+		stack.connect(&mut a, remote()).unwrap();
+		assert_eq!(a.port, Some(49153));
+	}
+}
@@ -1,5 +1,46 @@
 use no_std_net::SocketAddr;
 
+/// Size of the temporary stack buffer used by the default [`TcpClientStack::receive_with`] and
+/// [`TcpClientStack::send_with`] implementations when a stack does not override them for zero-copy.
+const FALLBACK_IO_BUFFER_SIZE: usize = 256;
+
+/// Classifies the general kind of failure behind a [`TcpClientStack::Error`].
+///
+/// This lets a portable client decide whether to reconnect or abort without
+/// having to know the concrete stack's error type, mirroring how downstream
+/// stacks wrap things like `std::io::ErrorKind::BrokenPipe` into a portable signal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TcpErrorKind {
+	/// The connection was gracefully closed by the remote peer.
+	PipeClosed,
+	/// The operation is not supported by this stack.
+	Unsupported,
+	/// Any other kind of error.
+	Other,
+}
+
+/// Implemented by [`TcpClientStack::Error`] so that callers can classify an
+/// error into a [`TcpErrorKind`] instead of matching on a stack-specific error type.
+///
+/// Requiring `From<TcpErrorKind>` lets default trait methods manufacture a
+/// portable error (e.g. [`TcpErrorKind::Unsupported`]) without knowing the
+/// concrete error type, the same way `std::io::Error: From<std::io::ErrorKind>` does.
+pub trait TcpError: From<TcpErrorKind> {
+	/// Classify this error.
+	fn kind(&self) -> TcpErrorKind;
+}
+
+/// Which direction(s) of a socket to shut down, mirroring `std::net::Shutdown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shutdown {
+	/// Stop receiving data, leaving the write half open.
+	Read,
+	/// Stop sending data, leaving the read half open.
+	Write,
+	/// Shut down both directions, equivalent to [`TcpClientStack::close`] without releasing the socket.
+	Both,
+}
+
 /// This trait is implemented by TCP/IP stacks. You could, for example, have an implementation
 /// which knows how to send AT commands to an ESP8266 WiFi module. You could have another implementation
 /// which knows how to driver the Rust Standard Library's `std::net` module. Given this trait, you can
@@ -8,7 +49,7 @@ pub trait TcpClientStack {
 	/// The type returned when we create a new TCP socket
 	type TcpSocket;
 	/// The type returned when we have an error
-	type Error: core::fmt::Debug;
+	type Error: core::fmt::Debug + TcpError;
 
 	/// Open a socket for usage as a TCP client.
 	///
@@ -54,6 +95,87 @@ pub trait TcpClientStack {
 	/// Close an existing TCP socket.
 	fn close(&mut self, socket: Self::TcpSocket) -> Result<(), Self::Error>;
 
+	/// Shut down the read half, write half, or both halves of the socket, without releasing it.
+	///
+	/// Unlike [`close`](Self::close), the socket remains usable afterwards for whichever
+	/// direction was not shut down, e.g. to keep reading a peer's response after signalling
+	/// that no more data will be written.
+	///
+	/// The default implementation returns [`TcpErrorKind::Unsupported`] so that existing
+	/// implementations keep compiling; stacks which support half-close should override it.
+	fn shutdown(&mut self, _socket: &mut Self::TcpSocket, _how: Shutdown) -> Result<(), Self::Error> {
+		Err(TcpErrorKind::Unsupported.into())
+	}
+
+	/// Set a read/write timeout on the given socket, or `None` to block indefinitely.
+	///
+	/// Once a timeout elapses, [`connect`](Self::connect), [`send`](Self::send) and
+	/// [`receive`](Self::receive) must return a [`TcpErrorKind`] distinguishable from
+	/// [`nb::Error::WouldBlock`] rather than continuing to block.
+	///
+	/// The default implementation returns [`TcpErrorKind::Unsupported`] so that existing
+	/// implementations keep compiling; stacks which support per-socket timers should override it.
+	fn set_timeout(
+		&mut self,
+		_socket: &mut Self::TcpSocket,
+		_timeout: Option<core::time::Duration>,
+	) -> Result<(), Self::Error> {
+		Err(TcpErrorKind::Unsupported.into())
+	}
+
+	/// Receive data from the stream without forcing a copy into a caller-provided buffer.
+	///
+	/// `f` is handed the largest contiguous slice of received bytes available right now and
+	/// must return `(consumed, result)`, where `consumed` is how many of those bytes it used.
+	/// Returns `f`'s `result`, or [`nb::Error::WouldBlock`] if no data has been received yet.
+	///
+	/// The default implementation copies into a small stack buffer via [`receive`](Self::receive),
+	/// which removes the bytes from the socket's RX queue before `f` ever sees them; there is
+	/// nowhere to put back anything `f` doesn't consume. Because of that, **the default
+	/// implementation requires `consumed == ` the length of the slice it was given**, and checks
+	/// this with `debug_assert!`. In a release build, where `debug_assert!` is compiled out,
+	/// `consumed < n` is *not* caught: the unconsumed bytes are silently dropped rather than
+	/// panicking. Only an override that exposes the real contiguous RX buffer, leaving unconsumed
+	/// bytes in place for the next call, may honor a smaller `consumed` correctly.
+	fn receive_with<F, R>(&mut self, socket: &mut Self::TcpSocket, f: F) -> nb::Result<R, Self::Error>
+	where
+		F: FnOnce(&mut [u8]) -> (usize, R),
+	{
+		let mut buffer = [0u8; FALLBACK_IO_BUFFER_SIZE];
+		let n = self.receive(socket, &mut buffer)?;
+		let (consumed, result) = f(&mut buffer[..n]);
+		debug_assert_eq!(
+			consumed, n,
+			"the fallback receive_with already removed these bytes from the socket; \
+			 f must consume the whole slice it was given"
+		);
+		Ok(result)
+	}
+
+	/// Write to the stream without forcing a copy out of a caller-provided buffer.
+	///
+	/// `f` is handed the largest contiguous slice of free space in the TX buffer available right
+	/// now and must return `(produced, result)`, where `produced` is how many bytes it wrote into
+	/// it. Returns `f`'s `result` once all `produced` bytes have been handed to the stack.
+	///
+	/// The default implementation fills a small stack buffer and then blocks on
+	/// [`send`](Self::send), looping the same way [`StackAndSocket`]'s `uWrite` impl does, until
+	/// every produced byte has actually been written; stacks that expose their internal TX buffer
+	/// directly should override it for true zero-copy.
+	fn send_with<F, R>(&mut self, socket: &mut Self::TcpSocket, f: F) -> nb::Result<R, Self::Error>
+	where
+		F: FnOnce(&mut [u8]) -> (usize, R),
+	{
+		let mut buffer = [0u8; FALLBACK_IO_BUFFER_SIZE];
+		let (produced, result) = f(&mut buffer);
+		let mut cursor = 0;
+		while cursor < produced {
+			let n = nb::block!(self.send(socket, &buffer[cursor..produced]))?;
+			cursor += n;
+		}
+		Ok(result)
+	}
+
 	/// create a tuple referencing the TcpClientStack and a TcpSocket that has all the information necessary to read and write data.
 	fn with_socket<'a>(&'a mut self, socket: &'a mut Self::TcpSocket) -> StackAndSocket<'a, Self>
 	where
@@ -87,6 +209,42 @@ pub trait TcpFullStack: TcpClientStack {
 		&mut self,
 		socket: &mut Self::TcpSocket,
 	) -> nb::Result<(Self::TcpSocket, SocketAddr), Self::Error>;
+
+	/// Create a blocking accept loop over `listening`, so a server can accept one connection at a
+	/// time via [`Incoming::next`] instead of hand-rolling an `nb::block!`/`WouldBlock` loop.
+	fn incoming<'a>(&'a mut self, listening: &'a mut Self::TcpSocket) -> Incoming<'a, Self>
+	where
+		Self: Sized,
+	{
+		Incoming::new(self, listening)
+	}
+}
+
+/// Blocking accept loop returned by [`TcpFullStack::incoming`].
+pub struct Incoming<'a, TFS>
+where
+	TFS: TcpFullStack,
+{
+	stack: &'a mut TFS,
+	listening: &'a mut TFS::TcpSocket,
+}
+
+impl<'a, TFS> Incoming<'a, TFS>
+where
+	TFS: TcpFullStack,
+{
+	/// create a new [Incoming] from the stack and listening-socket references
+	fn new(stack: &'a mut TFS, listening: &'a mut TFS::TcpSocket) -> Self {
+		Incoming { stack, listening }
+	}
+
+	/// Block until a connection is accepted on the listening socket.
+	///
+	/// Drives [`TcpFullStack::accept`] past [`nb::Error::WouldBlock`], returning the new socket
+	/// and its peer address once a connection is ready.
+	pub fn next(&mut self) -> Result<(TFS::TcpSocket, SocketAddr), TFS::Error> {
+		nb::block!(self.stack.accept(self.listening))
+	}
 }
 
 impl<T: TcpClientStack> TcpClientStack for &mut T {
@@ -129,6 +287,32 @@ impl<T: TcpClientStack> TcpClientStack for &mut T {
 	fn close(&mut self, socket: Self::TcpSocket) -> Result<(), Self::Error> {
 		T::close(self, socket)
 	}
+
+	fn shutdown(&mut self, socket: &mut Self::TcpSocket, how: Shutdown) -> Result<(), Self::Error> {
+		T::shutdown(self, socket, how)
+	}
+
+	fn set_timeout(
+		&mut self,
+		socket: &mut Self::TcpSocket,
+		timeout: Option<core::time::Duration>,
+	) -> Result<(), Self::Error> {
+		T::set_timeout(self, socket, timeout)
+	}
+
+	fn receive_with<F, R>(&mut self, socket: &mut Self::TcpSocket, f: F) -> nb::Result<R, Self::Error>
+	where
+		F: FnOnce(&mut [u8]) -> (usize, R),
+	{
+		T::receive_with(self, socket, f)
+	}
+
+	fn send_with<F, R>(&mut self, socket: &mut Self::TcpSocket, f: F) -> nb::Result<R, Self::Error>
+	where
+		F: FnOnce(&mut [u8]) -> (usize, R),
+	{
+		T::send_with(self, socket, f)
+	}
 }
 
 //
@@ -183,6 +367,34 @@ where
 	pub fn send(&mut self, buffer: &[u8]) -> nb::Result<usize, TCS::Error> {
 		self.tcp_stack.send(self.socket, buffer)
 	}
+
+	/// Shut down the read half, write half, or both halves of the socket, without releasing it.
+	pub fn shutdown(&mut self, how: Shutdown) -> Result<(), TCS::Error> {
+		self.tcp_stack.shutdown(self.socket, how)
+	}
+
+	/// Set a read/write timeout on the underlying socket, or `None` to block indefinitely.
+	pub fn set_timeout(&mut self, timeout: Option<core::time::Duration>) -> Result<(), TCS::Error> {
+		self.tcp_stack.set_timeout(self.socket, timeout)
+	}
+
+	/// Receive data from the stream without forcing a copy into a caller-provided buffer.
+	/// See [`TcpClientStack::receive_with`].
+	pub fn receive_with<F, R>(&mut self, f: F) -> nb::Result<R, TCS::Error>
+	where
+		F: FnOnce(&mut [u8]) -> (usize, R),
+	{
+		self.tcp_stack.receive_with(self.socket, f)
+	}
+
+	/// Write to the stream without forcing a copy out of a caller-provided buffer.
+	/// See [`TcpClientStack::send_with`].
+	pub fn send_with<F, R>(&mut self, f: F) -> nb::Result<R, TCS::Error>
+	where
+		F: FnOnce(&mut [u8]) -> (usize, R),
+	{
+		self.tcp_stack.send_with(self.socket, f)
+	}
 }
 
 impl<'a, TCS> ufmt::uWrite for StackAndSocket<'a, TCS>
@@ -202,3 +414,138 @@ where
 		Ok(())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[derive(Debug)]
+	struct MockError;
+
+	impl TcpError for MockError {
+		fn kind(&self) -> TcpErrorKind {
+			TcpErrorKind::Other
+		}
+	}
+
+	impl From<TcpErrorKind> for MockError {
+		fn from(_: TcpErrorKind) -> Self {
+			MockError
+		}
+	}
+
+	struct MockSocket;
+
+	/// A stack whose `send` only accepts `send_chunk` bytes per call (to exercise
+	/// `send_with`'s partial-write loop) and whose `receive` hands back a fixed payload once
+	/// (to exercise `receive_with`'s slice-forwarding).
+	struct MockStack {
+		send_chunk: usize,
+		sent: [u8; FALLBACK_IO_BUFFER_SIZE],
+		sent_len: usize,
+		rx_payload: [u8; FALLBACK_IO_BUFFER_SIZE],
+		rx_len: usize,
+		rx_delivered: bool,
+	}
+
+	impl MockStack {
+		fn with_send_chunk(send_chunk: usize) -> Self {
+			MockStack {
+				send_chunk,
+				sent: [0u8; FALLBACK_IO_BUFFER_SIZE],
+				sent_len: 0,
+				rx_payload: [0u8; FALLBACK_IO_BUFFER_SIZE],
+				rx_len: 0,
+				rx_delivered: false,
+			}
+		}
+
+		fn with_rx_payload(payload: &[u8]) -> Self {
+			let mut stack = MockStack::with_send_chunk(FALLBACK_IO_BUFFER_SIZE);
+			stack.rx_payload[..payload.len()].copy_from_slice(payload);
+			stack.rx_len = payload.len();
+			stack
+		}
+	}
+
+	impl TcpClientStack for MockStack {
+		type TcpSocket = MockSocket;
+		type Error = MockError;
+
+		fn socket(&mut self) -> Result<Self::TcpSocket, Self::Error> {
+			Ok(MockSocket)
+		}
+
+		fn connect(
+			&mut self,
+			_socket: &mut Self::TcpSocket,
+			_remote: SocketAddr,
+		) -> nb::Result<(), Self::Error> {
+			Ok(())
+		}
+
+		fn is_connected(&mut self, _socket: &Self::TcpSocket) -> Result<bool, Self::Error> {
+			Ok(true)
+		}
+
+		fn send(
+			&mut self,
+			_socket: &mut Self::TcpSocket,
+			buffer: &[u8],
+		) -> nb::Result<usize, Self::Error> {
+			let n = buffer.len().min(self.send_chunk);
+			self.sent[self.sent_len..self.sent_len + n].copy_from_slice(&buffer[..n]);
+			self.sent_len += n;
+			Ok(n)
+		}
+
+		fn receive(
+			&mut self,
+			_socket: &mut Self::TcpSocket,
+			buffer: &mut [u8],
+		) -> nb::Result<usize, Self::Error> {
+			if self.rx_delivered {
+				return Err(nb::Error::WouldBlock);
+			}
+			buffer[..self.rx_len].copy_from_slice(&self.rx_payload[..self.rx_len]);
+			self.rx_delivered = true;
+			Ok(self.rx_len)
+		}
+
+		fn close(&mut self, _socket: Self::TcpSocket) -> Result<(), Self::Error> {
+			Ok(())
+		}
+	}
+
+	#[test]
+	fn send_with_delivers_every_produced_byte_despite_short_writes() {
+		let mut stack = MockStack::with_send_chunk(3);
+		let mut socket = stack.socket().unwrap();
+		let payload = b"hello, world!";
+
+		stack
+			.send_with(&mut socket, |buf| {
+				buf[..payload.len()].copy_from_slice(payload);
+				(payload.len(), ())
+			})
+			.unwrap();
+
+		assert_eq!(&stack.sent[..stack.sent_len], payload);
+	}
+
+	#[test]
+	fn receive_with_passes_the_full_received_slice_to_the_closure() {
+		let payload = b"some bytes";
+		let mut stack = MockStack::with_rx_payload(payload);
+		let mut socket = stack.socket().unwrap();
+
+		let received_len = stack
+			.receive_with(&mut socket, |buf| {
+				assert_eq!(buf, payload);
+				(buf.len(), buf.len())
+			})
+			.unwrap();
+
+		assert_eq!(received_len, payload.len());
+	}
+}